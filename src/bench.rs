@@ -0,0 +1,44 @@
+//! `bench` subcommand: runs the solver against every possible secret for a
+//! given `(length, n_symbols)` in parallel and reports the distribution of
+//! guesses needed to solve each one.
+
+use std::collections::HashMap;
+
+use rayon::prelude::*;
+
+use crate::{solver, validate_length, validate_nsymbols, validate_unique};
+
+/// Runs the minimax solver against every possible secret code in parallel
+/// and prints the max, mean and histogram of guesses-to-solve.
+pub(crate) fn run(length: u32, n_symbols: u8, unique: bool) -> Result<(), &'static str> {
+    validate_length(length).expect("Validation error: incorrect code length");
+    validate_nsymbols(n_symbols).expect("Validation error: incorrect number of symbols");
+    validate_unique(length, n_symbols, unique).expect("Validation error: incorrect unique constraint");
+
+    let all = solver::all_codes(length as usize, n_symbols, unique);
+    info!("Benchmarking the solver against {} possible secrets", all.len());
+
+    let guesses: Vec<u32> = all.par_iter()
+        .map(|&secret| solver::guesses_to_solve(secret, length as usize, n_symbols, unique, &all))
+        .collect();
+
+    let max = *guesses.iter().max().expect("there is always at least one possible secret");
+    let mean = guesses.iter().sum::<u32>() as f64 / guesses.len() as f64;
+
+    let mut histogram: HashMap<u32, u32> = HashMap::new();
+    for &count in &guesses {
+        *histogram.entry(count).or_insert(0) += 1;
+    }
+    let mut histogram: Vec<(u32, u32)> = histogram.into_iter().collect();
+    histogram.sort_by_key(|&(turns, _)| turns);
+
+    println!("Secrets solved: {}", guesses.len());
+    println!("Max guesses: {}", max);
+    println!("Mean guesses: {:.3}", mean);
+    println!("Histogram of guesses-to-solve:");
+    for (turns, count) in histogram {
+        println!("  {}: {}", turns, count);
+    }
+
+    Ok(())
+}
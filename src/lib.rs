@@ -1,9 +1,83 @@
+use std::collections::HashMap;
 use std::io::{self};
 #[macro_use] extern crate log;
 extern crate thiserror;
+use ansi_term::Colour;
 use thiserror::Error;
+use rand::seq::SliceRandom;
 use rand::Rng;
 
+mod bench;
+mod packed;
+mod solver;
+
+/// Controls whether feedback pegs and (when shown) the secret are rendered
+/// with ANSI colors, following `--color` conventions used by other CLI tools
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorMode {
+    /// Colorize only when stdout is a terminal
+    Auto,
+    /// Always colorize, even when piped
+    Always,
+    /// Never colorize
+    Never,
+}
+
+impl std::str::FromStr for ColorMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(ColorMode::Auto),
+            "always" => Ok(ColorMode::Always),
+            "never" => Ok(ColorMode::Never),
+            other => Err(format!("Unknown color mode `{}` (expected auto, always or never)", other)),
+        }
+    }
+}
+
+impl ColorMode {
+    fn should_paint(self) -> bool {
+        match self {
+            ColorMode::Always => true,
+            ColorMode::Never => false,
+            ColorMode::Auto => atty::is(atty::Stream::Stdout),
+        }
+    }
+}
+
+/// A distinct terminal color for each of the up to `MAX_SYMBOLS` symbols
+const SYMBOL_COLOURS: [Colour; MAX_SYMBOLS as usize] = [
+    Colour::Fixed(196), Colour::Fixed(202), Colour::Fixed(208), Colour::Fixed(214),
+    Colour::Fixed(220), Colour::Fixed(190), Colour::Fixed(154), Colour::Fixed(118),
+    Colour::Fixed(82), Colour::Fixed(46), Colour::Fixed(49), Colour::Fixed(51),
+    Colour::Fixed(45), Colour::Fixed(39), Colour::Fixed(33), Colour::Fixed(27),
+    Colour::Fixed(21), Colour::Fixed(93), Colour::Fixed(129), Colour::Fixed(165),
+];
+
+/// Renders a feedback peg sequence ('X'/'O'/'-'), colorizing each peg when `color` allows it
+pub(crate) fn paint_pegs(pegs: &str, color: ColorMode) -> String {
+    if !color.should_paint() {
+        return pegs.to_string();
+    }
+    pegs.chars().map(|peg| match peg {
+        'X' => Colour::Green.bold().paint("X").to_string(),
+        'O' => Colour::Yellow.paint("O").to_string(),
+        _ => Colour::Fixed(244).paint("-").to_string(),
+    }).collect()
+}
+
+/// Renders a code as colored blocks, one per symbol, when `color` allows it
+pub(crate) fn paint_code(code: &str, color: ColorMode) -> String {
+    if !color.should_paint() {
+        return code.to_string();
+    }
+    code.chars().map(|symbol| {
+        let idx = CHARSET.iter().position(|&c| c == symbol as u8).unwrap_or(0);
+        SYMBOL_COLOURS[idx].paint("██").to_string()
+    }).collect()
+}
+
 /// A type to represent the output of validate_input
 pub type ValidationResult = std::result::Result<(), ValidationError>;
 /// Max number of attempts to guess
@@ -16,13 +90,8 @@ const MIN_LENGTH: u32 = 4;
 const MAX_SYMBOLS: u8 = 20;
 /// Min number of symbols (colors to choose)
 const MIN_SYMBOLS: u8 = 2;
-
-/// Finds all patterns in a String, returning the indexes in Vec<usize>
-macro_rules! findall {
-    ($x: ident, $y: ident) => {
-        $x.match_indices($y).map(|(idx, _)| idx).collect()
-    }
-}
+/// The symbols codes and guesses are made of
+const CHARSET: &[u8] = b"ABCDEFGHIJKLMNOPQRST";
 
 #[derive(Error, Debug)]
 /// Custom error to represent all possible errors that might arise parsing user input
@@ -94,17 +163,37 @@ pub fn validate_nsymbols(length: u8) -> ValidationResult {
     Ok(())
 }
 
-/// Creates a random string of `length` using up to `n_symbols` different symbols
-fn create_secret_code(length: u32, n_symbols: u8) -> String {
+/// Validates user input: a `unique` code can only exist if there are at
+/// least as many symbols to choose from as positions to fill
+pub fn validate_unique(length: u32, n_symbols: u8, unique: bool) -> ValidationResult {
+
+    if unique && length > u32::from(n_symbols) {
+        return Err(ValidationError::Invalid(
+            format!("Cannot make a {}-long code of unique colors out of only {} colors", length, n_symbols))
+        )
+    }
+    Ok(())
+}
+
+/// Creates a random string of `length` using up to `n_symbols` different
+/// symbols. When `unique` is set, no symbol is repeated.
+fn create_secret_code(length: u32, n_symbols: u8, unique: bool) -> String {
 
-    const CHARSET: &[u8] = b"ABCDEFGHIJKLMNOPQRST";
     let mut rng = rand::thread_rng();
 
     info!("Creating random number");
-    let secret: String = (0..length).map(|_| {
-            let idx: usize = usize::from(rng.gen_range(0u8, n_symbols));
-            CHARSET[idx] as char
-        }).collect();
+    let indexes: Vec<u8> = if unique {
+        let mut symbols: Vec<u8> = (0..n_symbols).collect();
+        symbols.shuffle(&mut rng);
+        symbols.truncate(length as usize);
+        symbols
+    } else {
+        (0..length).map(|_| rng.gen_range(0u8, n_symbols)).collect()
+    };
+    let packed = packed::pack(&indexes);
+    let secret: String = (0..length as usize)
+        .map(|i| CHARSET[packed::symbol_at(packed, i) as usize] as char)
+        .collect();
     debug!("Secret code chosen: '{}'", &secret);
     secret
 }
@@ -120,73 +209,256 @@ fn get_user_guess() -> String {
     buffer
 }
 
-/// Compares the secret code with the user guess. If they do not match,
-/// return as error the sequence of 'X', 'O' where:
-///   * 'X' is an exact match (symbol and position)
-///   * 'O' matches a symbol, but not a position
-fn check_user_guess(secret: &String, guess: &String) -> String {
+/// Counts exact ('X'), color-only ('O') and unmatched ('-') pegs between a
+/// `secret` and a `guess`, using the standard two-pass Mastermind algorithm
+/// so that repeated symbols are scored correctly: the first pass removes
+/// exact matches from consideration, the second pass sums, for each symbol,
+/// the smaller of its remaining count in the secret and in the guess.
+pub(crate) fn count_matches(secret: &str, guess: &str) -> (u8, u8, u8) {
 
-    let mut result = String::new();
-    let mut results = (0u8, 0u8, 0u8);  // number of X, O, -
-    for (index, c) in guess.char_indices() {
-        let indexes: Vec<usize> = findall!(secret, c);
-        if indexes.is_empty() {
-            results.2 += 1
+    let mut exact = 0u8;
+    let mut secret_counts: HashMap<char, u8> = HashMap::new();
+    let mut guess_counts: HashMap<char, u8> = HashMap::new();
+
+    for (s, g) in secret.chars().zip(guess.chars()) {
+        if s == g {
+            exact += 1;
         } else {
-            if indexes.into_iter().find(|&x| x == index).is_some() {
-                results.0 += 1
-            } else {
-                results.1 += 1
-            }
+            *secret_counts.entry(s).or_insert(0) += 1;
+            *guess_counts.entry(g).or_insert(0) += 1;
         }
     }
-    let mut index: usize = 0;
-    for _idx in 0..results.0 {
-        result.insert(index, 'X');
-        index += 1;
+
+    let color_only: u8 = guess_counts.iter()
+        .map(|(symbol, &count)| count.min(*secret_counts.get(symbol).unwrap_or(&0)))
+        .sum();
+    let none = guess.chars().count() as u8 - exact - color_only;
+
+    (exact, color_only, none)
+}
+
+/// Validates that a submitted guess is the right length and only uses
+/// symbols from the game's alphabet (the first `n_symbols` of `CHARSET`)
+fn validate_guess(guess: &str, length: u32, n_symbols: u8) -> ValidationResult {
+
+    if guess.chars().count() as u32 != length {
+        return Err(ValidationError::Invalid(
+            format!("Guess must be {} symbols long", length))
+        )
     }
-    for _idx in 0..results.1 {
-        result.insert(index, 'O');
-        index += 1;
+    let alphabet = &CHARSET[..n_symbols as usize];
+    if let Some(symbol) = guess.chars().find(|&c| !c.is_ascii() || !alphabet.contains(&(c as u8))) {
+        return Err(ValidationError::Invalid(
+            format!("`{}` is not a valid symbol (use A-{})", symbol, alphabet[alphabet.len() - 1] as char))
+        )
     }
-    for _idx in 0..results.2 {
-        result.insert(index, '-');
-        index += 1;
+    Ok(())
+}
+
+/// The length, alphabet and attempt budget a [`Game`] is played with
+#[derive(Debug, Clone, Copy)]
+pub struct Config {
+    pub length: u32,
+    pub n_symbols: u8,
+    pub unique: bool,
+    pub attempts: u32,
+}
+
+/// The structured result of scoring one guess against the secret
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Feedback {
+    pub exact: u8,
+    pub color_only: u8,
+    pub none: u8,
+}
+
+impl Feedback {
+    /// Renders the feedback as a peg string: 'X' exact, 'O' color-only, '-' none
+    pub fn pegs(&self) -> String {
+        let mut result = "X".repeat(self.exact as usize);
+        result.push_str(&"O".repeat(self.color_only as usize));
+        result.push_str(&"-".repeat(self.none as usize));
+        result
     }
-    result
 }
 
-/// Main application loop, generates the secret code and allows the user
-/// to input guesses, calculating and printing the result
-pub fn run(attempts: u32, length: u32, n_symbols: u8) -> Result<(), &'static str> {
+/// Whether a [`Game`] is still being played, was won, or was lost
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameOutcome {
+    InProgress,
+    Won,
+    Lost,
+}
 
-    validate_attempts(attempts).expect("Validation error: incorrect attempts");
-    validate_length(length).expect("Validation error: incorrect code length");
-    validate_nsymbols(n_symbols).expect("Validation error: incorrect number of symbols");
+/// An I/O-free Mastermind engine: holds the secret, the config it was
+/// created with, and how many guesses have been made so far. Driving it
+/// (reading guesses, printing feedback) is left to the caller, so the same
+/// engine can back the human-guessing loop, the solver, the benchmark
+/// harness, and automated tests alike.
+pub struct Game {
+    secret: String,
+    config: Config,
+    guesses_made: u32,
+    outcome: GameOutcome,
+}
+
+impl Game {
+    /// Creates a new game with a freshly generated secret for `config`
+    pub fn new(config: Config) -> Game {
+        validate_attempts(config.attempts).expect("Validation error: incorrect attempts");
+        validate_length(config.length).expect("Validation error: incorrect code length");
+        validate_nsymbols(config.n_symbols).expect("Validation error: incorrect number of symbols");
+        validate_unique(config.length, config.n_symbols, config.unique).expect("Validation error: incorrect unique constraint");
+
+        let secret = create_secret_code(config.length, config.n_symbols, config.unique);
+        Game { secret, config, guesses_made: 0, outcome: GameOutcome::InProgress }
+    }
 
-    let secret = create_secret_code(length, n_symbols);
-    let mut expected = String::with_capacity(length as usize);
-    for _ in 0..length {
-        expected.push('X');
+    #[cfg(test)]
+    fn with_secret(secret: String, config: Config) -> Game {
+        Game { secret, config, guesses_made: 0, outcome: GameOutcome::InProgress }
+    }
+
+    /// The code the player is trying to guess
+    pub fn secret(&self) -> &str {
+        &self.secret
+    }
+
+    /// How the game currently stands
+    pub fn outcome(&self) -> GameOutcome {
+        self.outcome
+    }
+
+    /// How many guesses have been made so far
+    pub fn guesses_made(&self) -> u32 {
+        self.guesses_made
+    }
+
+    /// Submits a guess, validating its length and alphabet, and scores it
+    /// against the secret. Updates and returns the game's outcome. An invalid
+    /// guess still counts against the attempt budget, so a player (or a
+    /// closed/EOF input stream) repeatedly submitting invalid guesses cannot
+    /// loop forever without ever running out of attempts.
+    pub fn guess(&mut self, guess: &str) -> Result<(Feedback, GameOutcome), ValidationError> {
+        if self.outcome != GameOutcome::InProgress {
+            return Err(ValidationError::Invalid(String::from("The game is already over")))
+        }
+        if let Err(e) = validate_guess(guess, self.config.length, self.config.n_symbols) {
+            self.guesses_made += 1;
+            if self.guesses_made >= self.config.attempts {
+                self.outcome = GameOutcome::Lost;
+            }
+            return Err(e)
+        }
+
+        let (exact, color_only, none) = count_matches(&self.secret, guess);
+        let feedback = Feedback { exact, color_only, none };
+        self.guesses_made += 1;
+
+        self.outcome = if exact as u32 == self.config.length {
+            GameOutcome::Won
+        } else if self.guesses_made >= self.config.attempts {
+            GameOutcome::Lost
+        } else {
+            GameOutcome::InProgress
+        };
+
+        Ok((feedback, self.outcome))
+    }
+}
+
+/// Plays a single round: generates the secret code, lets the user input
+/// guesses, and calculates and prints the result of each one. Returns the
+/// number of guesses it took to win.
+fn play_round(config: Config, color: ColorMode, cheat: bool) -> Result<u32, &'static str> {
+
+    let mut game = Game::new(config);
+    if cheat {
+        println!("{}", paint_code(game.secret(), color));
     }
-    println!("{}", secret);
-    let mut guesses: u32 = 1;
     loop {
         let guess = get_user_guess();
         debug!("User guessed: '{}'", guess);
-        let guess_result = check_user_guess(&secret, &guess);
-        if guess_result == expected {
-            return Ok(())
+        match game.guess(&guess) {
+            Ok((_, GameOutcome::Won)) => return Ok(game.guesses_made()),
+            Ok((feedback, GameOutcome::Lost)) => {
+                println!("Nope: {}", paint_pegs(&feedback.pegs(), color));
+                return Err("Max number of attempts reached")
+            }
+            Ok((feedback, GameOutcome::InProgress)) => {
+                println!("Nope: {}", paint_pegs(&feedback.pegs(), color));
+                debug!("User guess did not match: retry");
+            }
+            Err(e) => {
+                eprintln!("{}", e);
+                if game.outcome() == GameOutcome::Lost {
+                    return Err("Max number of attempts reached")
+                }
+            }
         }
-        println!("Nope: {}", guess_result);
-        if guesses >= attempts {
-            return Err("Max number of attempts reached");
+    }
+}
+
+/// Main application loop, generates the secret code and allows the user
+/// to input guesses, calculating and printing the result
+pub fn run(config: Config, color: ColorMode, cheat: bool) -> Result<(), &'static str> {
+    play_round(config, color, cheat).map(|_| ())
+}
+
+/// Tracks aggregate statistics across a sequence of replayed rounds:
+/// games played, games won/lost, and the number of guesses each win took
+pub struct Session {
+    games_played: u32,
+    guesses_to_win: Vec<u32>,
+}
+
+impl Session {
+    /// Creates an empty session with no rounds played yet
+    pub fn new() -> Session {
+        Session { games_played: 0, guesses_to_win: Vec::new() }
+    }
+
+    /// Plays one round and records whether it was won, and in how many guesses
+    pub fn play_round(&mut self, config: Config, color: ColorMode, cheat: bool) -> Result<(), &'static str> {
+        self.games_played += 1;
+        let guesses = play_round(config, color, cheat)?;
+        self.guesses_to_win.push(guesses);
+        Ok(())
+    }
+
+    /// Prints aggregate statistics for every round played so far
+    pub fn summary(&self) {
+        let games_won = self.guesses_to_win.len() as u32;
+        println!("Games played: {}", self.games_played);
+        println!("Games won: {}", games_won);
+        println!("Games lost: {}", self.games_played - games_won);
+        if let Some(&best) = self.guesses_to_win.iter().min() {
+            let average = self.guesses_to_win.iter().sum::<u32>() as f64 / self.guesses_to_win.len() as f64;
+            println!("Average guesses to win: {:.2}", average);
+            println!("Best win: {} guesses", best);
         }
-        debug!("User guess did not match: retry");
-        guesses += 1;
     }
 }
 
+impl Default for Session {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Computer-as-codebreaker mode: the user thinks of a secret code and the
+/// program deduces it using Knuth's minimax algorithm, reporting its guesses
+/// and reading back black/white feedback each turn.
+pub fn solve(length: u32, n_symbols: u8, unique: bool, color: ColorMode) -> Result<(), &'static str> {
+    solver::play(length, n_symbols, unique, color)
+}
+
+/// Runs the solver against every possible secret for a given `(length,
+/// n_symbols)` in parallel and reports the distribution of guesses-to-solve
+pub fn bench(length: u32, n_symbols: u8, unique: bool) -> Result<(), &'static str> {
+    bench::run(length, n_symbols, unique)
+}
+
 #[cfg(test)]
 mod tests {
     // Note this useful idiom: importing names from outer (for mod tests) scope.
@@ -209,13 +481,13 @@ mod tests {
 
     #[test]
     fn test_secret_code() {
-        let secret = create_secret_code(5, 1);
+        let secret = create_secret_code(5, 1, false);
         assert_eq!(secret.len(), 5);
         for c in secret.chars() {
             assert_eq!('\u{0041}', c);
         }
         // with only two symbols to create the secret code, each char should be 'A' or 'B'
-        let secret = create_secret_code(5, 2);
+        let secret = create_secret_code(5, 2, false);
         assert_eq!(secret.len(), 5);
         for c in secret.chars() {
             assert!('\u{0041}' == c || '\u{0042}' == c);
@@ -223,10 +495,71 @@ mod tests {
     }
 
     #[test]
-    fn test_check_user_guess() {
-        assert_eq!("----", check_user_guess(&String::from("AAAA"), &String::from("BBBB")));
-        assert_eq!("XX--", check_user_guess(&String::from("ABBA"), &String::from("ACCA")));
-        assert_eq!("OOOO", check_user_guess(&String::from("AABB"), &String::from("BBAA")));
-        assert_eq!("XXXX", check_user_guess(&String::from("ABCD"), &String::from("ABCD")));
+    fn test_count_matches() {
+        assert_eq!((0, 0, 4), count_matches("AAAA", "BBBB"));
+        assert_eq!((2, 0, 2), count_matches("ABBA", "ACCA"));
+        assert_eq!((0, 4, 0), count_matches("AABB", "BBAA"));
+        assert_eq!((4, 0, 0), count_matches("ABCD", "ABCD"));
+    }
+
+    #[test]
+    fn test_count_matches_repeated_colors() {
+        // three of the four positions are exact matches; the extra guessed
+        // 'A' has no spare secret 'A' left to pair with, so it scores '-'
+        // rather than the 'O' a naive per-occurrence count would give it
+        assert_eq!((3, 0, 1), count_matches("AABB", "AABA"));
+        // both of the secret's 'C's are already claimed by exact matches, so
+        // the two extra guessed 'C's are unmatched, not color-only hits
+        assert_eq!((2, 0, 2), count_matches("ABCC", "CCCC"));
+    }
+
+    fn test_config() -> Config {
+        Config { length: 4, n_symbols: 4, unique: false, attempts: 2 }
+    }
+
+    #[test]
+    fn test_game_guess_validation() {
+        // invalid guesses use up attempts, so allow enough of them that every
+        // guess below is rejected by validation rather than by the game ending
+        let mut game = Game::with_secret(String::from("ABCD"), Config { attempts: 10, ..test_config() });
+        game.guess("AB").expect_err("too short");
+        game.guess("ABCDE").expect_err("too long");
+        game.guess("ABCZ").expect_err("'Z' is outside the 4-symbol alphabet");
+        // 'Ł' (U+0141) truncated to a u8 via `as` would alias 'A' (0x41);
+        // it must be rejected as a non-ASCII symbol instead
+        game.guess("\u{141}BCD").expect_err("non-ASCII symbols must not alias low bytes of CHARSET");
+    }
+
+    #[test]
+    fn test_game_guess_win() {
+        let mut game = Game::with_secret(String::from("ABCD"), test_config());
+        let (feedback, outcome) = game.guess("ABCD").expect("valid guess");
+        assert_eq!(feedback, Feedback { exact: 4, color_only: 0, none: 0 });
+        assert_eq!(outcome, GameOutcome::Won);
+    }
+
+    #[test]
+    fn test_game_guess_loss() {
+        let mut game = Game::with_secret(String::from("ABCD"), test_config());
+        let (_, outcome) = game.guess("DCBA").expect("valid guess");
+        assert_eq!(outcome, GameOutcome::InProgress);
+        let (feedback, outcome) = game.guess("AAAA").expect("valid guess");
+        assert_eq!(feedback, Feedback { exact: 1, color_only: 0, none: 3 });
+        assert_eq!(outcome, GameOutcome::Lost);
+        game.guess("ABCD").expect_err("no guesses left once the game is over");
+    }
+
+    #[test]
+    fn test_game_invalid_guess_counts_as_attempt() {
+        // test_config() allows 2 attempts; both are spent on invalid guesses,
+        // so the game must end instead of accepting guesses forever
+        let mut game = Game::with_secret(String::from("ABCD"), test_config());
+        game.guess("TOOLONGGUESS").expect_err("too long");
+        assert_eq!(game.guesses_made(), 1);
+        assert_eq!(game.outcome(), GameOutcome::InProgress);
+        game.guess("Z").expect_err("too short and out of alphabet");
+        assert_eq!(game.guesses_made(), 2);
+        assert_eq!(game.outcome(), GameOutcome::Lost);
+        game.guess("ABCD").expect_err("no guesses left once the game is over");
     }
 }
\ No newline at end of file
@@ -1,4 +1,6 @@
+use std::io;
 use std::process;
+use mastermind::{ColorMode, Config, Session};
 use structopt::StructOpt;
 #[macro_use]
 extern crate log;
@@ -25,20 +27,85 @@ struct Opt {
     guesses: u32,
     /// Do not allow repeated colors in the code
     #[structopt(short, long)]
-    unique: bool
+    unique: bool,
+    /// Let the computer guess a secret code that you think of, instead of
+    /// the other way around
+    #[structopt(long)]
+    solve: bool,
+    /// Control colored output: auto, always or never
+    #[structopt(long, default_value = "auto")]
+    color: ColorMode,
+    /// Print the secret code before playing (only useful for debugging)
+    #[structopt(long)]
+    cheat: bool,
+    #[structopt(subcommand)]
+    command: Option<Command>
 }
 
+#[derive(StructOpt, Debug)]
+enum Command {
+    /// Runs the solver against every possible secret for a given code size
+    /// and reports the distribution of guesses-to-solve
+    Bench {
+        /// Number of different colors to use
+        #[structopt(short = "n", long = "ncolors", default_value = "4")]
+        colors: u8,
+        /// Length of the code to break
+        #[structopt(short, long, default_value = "4")]
+        length: u32,
+        /// Do not allow repeated colors in the code
+        #[structopt(short, long)]
+        unique: bool
+    }
+}
+
+/// Asks the user whether to play another round
+fn play_again() -> bool {
+    println!("Play again? [y/N]: ");
+    let mut buffer = String::new();
+    io::stdin().read_line(&mut buffer)
+        .expect("Had problems reading user input!");
+    matches!(buffer.trim().to_lowercase().as_str(), "y" | "yes")
+}
 
 fn main() {
     env_logger::init();
     let opt = Opt::from_args();
     println!("{:?}", opt);
     info!("Starting the game!");
-    if let Err(e) = mastermind::run(opt.guesses, opt.length,opt.colors, opt.unique) {
-        eprintln!("{}", e);
-        eprintln!("Sorry, but you lost!");
-        process::exit(2);
-    } else {
-        println!("Congratulations, you won!");
+
+    if let Some(Command::Bench { colors, length, unique }) = opt.command {
+        if let Err(e) = mastermind::bench(length, colors, unique) {
+            eprintln!("{}", e);
+            process::exit(2);
+        }
+        return;
+    }
+
+    if opt.solve {
+        if let Err(e) = mastermind::solve(opt.length, opt.colors, opt.unique, opt.color) {
+            eprintln!("{}", e);
+            eprintln!("Sorry, but you lost!");
+            process::exit(2);
+        } else {
+            println!("Congratulations, you won!");
+        }
+        return;
+    }
+
+    let config = Config { length: opt.length, n_symbols: opt.colors, unique: opt.unique, attempts: opt.guesses };
+    let mut session = Session::new();
+    loop {
+        match session.play_round(config, opt.color, opt.cheat) {
+            Ok(()) => println!("Congratulations, you won!"),
+            Err(e) => {
+                eprintln!("{}", e);
+                eprintln!("Sorry, but you lost!");
+            }
+        }
+        if !play_again() {
+            break;
+        }
     }
+    session.summary();
 }
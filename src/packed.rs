@@ -0,0 +1,80 @@
+//! Bit-packed code representation: a code is packed one symbol per byte into
+//! a single integer (similar to the classic word-to-`u64` encoding used for
+//! fixed-length word games), so the solver can score guesses without
+//! allocating a `String` or `Vec` for every comparison.
+
+use crate::MAX_SYMBOLS;
+
+/// Packs a sequence of symbol indices into a single integer, one symbol per byte
+pub(crate) fn pack(symbols: &[u8]) -> u128 {
+    symbols.iter()
+        .enumerate()
+        .fold(0u128, |packed, (position, &symbol)| packed | (u128::from(symbol) << (position * 8)))
+}
+
+/// Extracts the symbol index stored at `position` in a packed code
+pub(crate) fn symbol_at(packed: u128, position: usize) -> u8 {
+    ((packed >> (position * 8)) & 0xFF) as u8
+}
+
+/// Scores a packed `guess` against a packed `secret` of the given `length`,
+/// returning the (blacks, whites) pair. Uses fixed-size per-symbol count
+/// arrays instead of the `HashMap`-backed `count_matches` used for the
+/// human-facing game, since this runs once per candidate per turn.
+pub(crate) fn score(secret: u128, guess: u128, length: usize) -> (u8, u8) {
+    let mut exact = 0u8;
+    let mut secret_counts = [0u8; MAX_SYMBOLS as usize];
+    let mut guess_counts = [0u8; MAX_SYMBOLS as usize];
+
+    for position in 0..length {
+        let s = symbol_at(secret, position);
+        let g = symbol_at(guess, position);
+        if s == g {
+            exact += 1;
+        } else {
+            secret_counts[s as usize] += 1;
+            guess_counts[g as usize] += 1;
+        }
+    }
+
+    let whites: u8 = secret_counts.iter().zip(guess_counts.iter())
+        .map(|(&s, &g)| s.min(g))
+        .sum();
+
+    (exact, whites)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pack_symbol_at_roundtrip() {
+        let packed = pack(&[0, 3, 1, 2]);
+        assert_eq!(symbol_at(packed, 0), 0);
+        assert_eq!(symbol_at(packed, 1), 3);
+        assert_eq!(symbol_at(packed, 2), 1);
+        assert_eq!(symbol_at(packed, 3), 2);
+    }
+
+    #[test]
+    fn test_score_repeated_colors() {
+        // mirrors count_matches("AABB", "AABA") == (3, 0, 1): the extra
+        // guessed 'A' has no spare secret 'A' left to pair with
+        let secret = pack(&[0, 0, 1, 1]);
+        let guess = pack(&[0, 0, 1, 0]);
+        assert_eq!(score(secret, guess, 4), (3, 0));
+
+        // mirrors count_matches("ABCC", "CCCC") == (2, 0, 2): both secret
+        // 'C's are already claimed by exact matches
+        let secret = pack(&[0, 1, 2, 2]);
+        let guess = pack(&[2, 2, 2, 2]);
+        assert_eq!(score(secret, guess, 4), (2, 0));
+
+        // mirrors count_matches("AABB", "BBAA") == (0, 4, 0): no exact
+        // matches, but every symbol is present in the other code
+        let secret = pack(&[0, 0, 1, 1]);
+        let guess = pack(&[1, 1, 0, 0]);
+        assert_eq!(score(secret, guess, 4), (0, 4));
+    }
+}
@@ -0,0 +1,192 @@
+//! Computer-as-codebreaker mode: the human secretly picks a code and the
+//! program deduces it, turn by turn, using Knuth's minimax strategy.
+
+use std::collections::HashSet;
+use std::io;
+
+use crate::packed;
+use crate::{paint_code, validate_length, validate_nsymbols, validate_unique, ColorMode, CHARSET};
+
+/// Enumerates every code of `length` over `n_symbols` symbols (0-indexed),
+/// respecting `unique`, as bit-packed integers.
+pub(crate) fn all_codes(length: usize, n_symbols: u8, unique: bool) -> Vec<u128> {
+    let mut codes = Vec::new();
+    let mut current = Vec::with_capacity(length);
+    let mut used = vec![false; n_symbols as usize];
+    build_codes(length, n_symbols, unique, &mut current, &mut used, &mut codes);
+    codes
+}
+
+fn build_codes(
+    remaining: usize,
+    n_symbols: u8,
+    unique: bool,
+    current: &mut Vec<u8>,
+    used: &mut Vec<bool>,
+    out: &mut Vec<u128>,
+) {
+    if remaining == 0 {
+        out.push(packed::pack(current));
+        return;
+    }
+    for symbol in 0..n_symbols {
+        if unique && used[symbol as usize] {
+            continue;
+        }
+        current.push(symbol);
+        used[symbol as usize] = true;
+        build_codes(remaining - 1, n_symbols, unique, current, used, out);
+        used[symbol as usize] = false;
+        current.pop();
+    }
+}
+
+/// Picks the fixed opening guess: `AABB...` when repeats are allowed, or
+/// `ABCD...` when the code must use unique symbols.
+fn first_guess(length: usize, n_symbols: u8, unique: bool) -> u128 {
+    let symbols: Vec<u8> = if unique {
+        (0..length as u8).map(|i| i % n_symbols).collect()
+    } else {
+        let second = if n_symbols > 1 { 1 } else { 0 };
+        (0..length).map(|i| if i < length / 2 { 0 } else { second }).collect()
+    };
+    packed::pack(&symbols)
+}
+
+/// Picks the next guess by Knuth's minimax rule: for every candidate guess
+/// (drawn from the whole code space), find the worst-case number of
+/// `remaining` codes that would still be consistent after playing it, then
+/// keep the candidate that minimizes that worst case, preferring one still
+/// present in `remaining` on ties.
+fn best_guess(candidates: &[u128], remaining: &[u128], length: usize) -> u128 {
+    let still_possible: HashSet<u128> = remaining.iter().copied().collect();
+
+    candidates
+        .iter()
+        .map(|&guess| {
+            // indexed by (exact, whites); both are at most `length` (<= MAX_LENGTH)
+            let mut buckets = [[0u32; crate::MAX_LENGTH as usize + 1]; crate::MAX_LENGTH as usize + 1];
+            for &secret in remaining {
+                let (exact, whites) = packed::score(secret, guess, length);
+                buckets[exact as usize][whites as usize] += 1;
+            }
+            let worst_case = buckets.iter().flatten().copied().max().unwrap_or(0);
+            (worst_case, !still_possible.contains(&guess), guess)
+        })
+        .min_by(|a, b| a.0.cmp(&b.0).then(a.1.cmp(&b.1)))
+        .map(|(_, _, guess)| guess)
+        .expect("the candidate code space is never empty")
+}
+
+fn code_to_string(code: u128, length: usize) -> String {
+    (0..length).map(|i| CHARSET[packed::symbol_at(code, i) as usize] as char).collect()
+}
+
+/// Reads the secret-holder's feedback for the last guess as `"<blacks> <whites>"`.
+fn get_feedback(length: u32) -> (u8, u8) {
+    loop {
+        println!("Blacks and whites for that guess (e.g. `2 1`): ");
+        let mut buffer = String::new();
+        io::stdin()
+            .read_line(&mut buffer)
+            .expect("Had problems reading user input!");
+        if let [blacks, whites] = buffer.split_whitespace().collect::<Vec<_>>()[..] {
+            if let (Ok(blacks), Ok(whites)) = (blacks.parse::<u8>(), whites.parse::<u8>()) {
+                if blacks as u32 + whites as u32 <= length {
+                    return (blacks, whites);
+                }
+            }
+        }
+        println!("Please enter two numbers that add up to at most {}", length);
+    }
+}
+
+/// Drives the minimax loop, asking `get_feedback_for` for the (blacks,
+/// whites) pair each turn, until it reports every peg exact. Returns `None`
+/// if the feedback given ever leaves no candidate secret consistent. Shared
+/// between the interactive [`play`] and the non-interactive
+/// [`guesses_to_solve`].
+fn run<F>(length: usize, n_symbols: u8, unique: bool, all: &[u128], mut get_feedback_for: F) -> Option<u32>
+where
+    F: FnMut(u128, u32) -> (u8, u8),
+{
+    let mut remaining = all.to_vec();
+    let mut guess = first_guess(length, n_symbols, unique);
+    let mut turn = 1u32;
+
+    loop {
+        let feedback = get_feedback_for(guess, turn);
+        if feedback == (length as u8, 0) {
+            return Some(turn);
+        }
+
+        remaining.retain(|&secret| packed::score(secret, guess, length) == feedback);
+        if remaining.is_empty() {
+            return None;
+        }
+
+        guess = if remaining.len() == 1 {
+            remaining[0]
+        } else {
+            best_guess(all, &remaining, length)
+        };
+        turn += 1;
+    }
+}
+
+/// Runs the computer-as-codebreaker session: the human keeps a secret code
+/// in mind and the program guesses it using Knuth's minimax algorithm,
+/// pruning the candidate space after each round of feedback until a single
+/// code remains or the feedback reports every peg exact.
+pub fn play(length: u32, n_symbols: u8, unique: bool, color: ColorMode) -> Result<(), &'static str> {
+    validate_length(length).expect("Validation error: incorrect code length");
+    validate_nsymbols(n_symbols).expect("Validation error: incorrect number of symbols");
+    validate_unique(length, n_symbols, unique).expect("Validation error: incorrect unique constraint");
+
+    let all = all_codes(length as usize, n_symbols, unique);
+
+    let result = run(length as usize, n_symbols, unique, &all, |guess, turn| {
+        println!("My guess #{}: {}", turn, paint_code(&code_to_string(guess, length as usize), color));
+        let feedback = get_feedback(length);
+        debug!("Feedback for guess {}: {:?}", code_to_string(guess, length as usize), feedback);
+        feedback
+    });
+
+    match result {
+        Some(turns) => {
+            println!("Solved it in {} guesses!", turns);
+            Ok(())
+        }
+        None => Err("No code is consistent with the feedback given - was it entered correctly?"),
+    }
+}
+
+/// Runs the solver against a known `secret` without any user interaction,
+/// returning the number of guesses it took. Used by the `bench` subcommand
+/// to measure solver performance across the whole code space.
+pub(crate) fn guesses_to_solve(secret: u128, length: usize, n_symbols: u8, unique: bool, all: &[u128]) -> u32 {
+    run(length, n_symbols, unique, all, |guess, _turn| packed::score(secret, guess, length))
+        .expect("scoring a guess against a known secret from `all` is always internally consistent")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_all_codes_counts() {
+        // 2 symbols, length 2, repeats allowed: AA, AB, BA, BB
+        assert_eq!(all_codes(2, 2, false).len(), 4);
+        // 2 symbols, length 2, unique: AB, BA
+        assert_eq!(all_codes(2, 2, true).len(), 2);
+        // 3 symbols, length 3, unique: every permutation of 3 symbols
+        assert_eq!(all_codes(3, 3, true).len(), 6);
+    }
+
+    #[test]
+    fn test_best_guess_picks_the_only_remaining_candidate() {
+        let all = all_codes(2, 2, false);
+        let remaining = vec![packed::pack(&[0, 0])];
+        assert_eq!(best_guess(&all, &remaining, 2), packed::pack(&[0, 0]));
+    }
+}